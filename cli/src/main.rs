@@ -1,6 +1,4 @@
 use log::debug;
-use std::collections::HashSet;
-use std::process::Child;
 use std::{
     env,
     error::Error,
@@ -8,53 +6,160 @@ use std::{
     process::exit,
     process::Command,
     process::Stdio,
-    sync::atomic::AtomicBool,
-    sync::atomic::Ordering,
-    sync::Arc,
     thread::sleep,
     time::{Duration, Instant},
 };
-use sysinfo::{Pid, ProcessRefreshKind, System};
+use sysinfo::{Pid, System};
+
+#[cfg(unix)]
+#[path = "unix.rs"]
+mod platform;
+#[cfg(windows)]
+#[path = "windows.rs"]
+mod platform;
+
+/// Default time to wait after the graceful termination request before
+/// escalating to a hard kill of the whole process tree. Overridable with
+/// `--grace-period`/`KILL_ORPHAN_GRACE_PERIOD`.
+const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(2);
+/// Default outer bound on the whole shutdown: if the child still hasn't
+/// exited this long after we started terminating it, give up. Overridable
+/// with `--timeout`/`KILL_ORPHAN_TIMEOUT`.
+const DEFAULT_GIVEUP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default interval at which the main loop polls for the child and parent
+/// state. Overridable with `--poll-interval`.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const USAGE: &str = "Usage: kill-orphan <command> [<args>...]
+
+Options (also settable via env vars where noted):
+  --timeout <secs>        give up waiting for the child this long after
+                           starting to terminate it (default: 5, env KILL_ORPHAN_TIMEOUT)
+  --grace-period <secs>   how long to wait after the graceful signal before
+                           escalating to a hard kill (default: 2, env KILL_ORPHAN_GRACE_PERIOD)
+  --poll-interval <ms>    how often to poll for child/parent state (default: 100)
+  --signal <SIGNAL>       signal to send when the parent disappears (env KILL_ORPHAN_SIGNAL)";
+
+struct Config {
+    giveup_timeout: Duration,
+    grace_period: Duration,
+    poll_interval: Duration,
+    signal: i32,
+    command: Vec<String>,
+}
+
+/// Parses `--timeout`/`--grace-period`/`--poll-interval`/`--signal` off the
+/// front of `args`, falling back to `KILL_ORPHAN_TIMEOUT`/
+/// `KILL_ORPHAN_GRACE_PERIOD`/`KILL_ORPHAN_SIGNAL` and then the defaults
+/// above. Everything from the first argument that isn't one of these four
+/// flags on (an unrecognised `--flag` included) is the command to run.
+fn parse_args(args: &[String]) -> Result<Config, &'static str> {
+    let mut giveup_timeout = env::var("KILL_ORPHAN_TIMEOUT")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_GIVEUP_TIMEOUT);
+    let mut grace_period = env::var("KILL_ORPHAN_GRACE_PERIOD")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_GRACE_PERIOD);
+    let mut poll_interval = DEFAULT_POLL_INTERVAL;
+    let mut signal = env::var("KILL_ORPHAN_SIGNAL")
+        .ok()
+        .and_then(|s| platform::parse_signal(&s))
+        .unwrap_or(platform::GRACEFUL_SIGNAL);
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--timeout" => {
+                i += 1;
+                let secs: u64 = args
+                    .get(i)
+                    .ok_or("--timeout requires a value")?
+                    .parse()
+                    .map_err(|_| "--timeout expects a number of seconds")?;
+                giveup_timeout = Duration::from_secs(secs);
+            }
+            "--grace-period" => {
+                i += 1;
+                let secs: u64 = args
+                    .get(i)
+                    .ok_or("--grace-period requires a value")?
+                    .parse()
+                    .map_err(|_| "--grace-period expects a number of seconds")?;
+                grace_period = Duration::from_secs(secs);
+            }
+            "--poll-interval" => {
+                i += 1;
+                let millis: u64 = args
+                    .get(i)
+                    .ok_or("--poll-interval requires a value")?
+                    .parse()
+                    .map_err(|_| "--poll-interval expects a number of milliseconds")?;
+                poll_interval = Duration::from_millis(millis);
+            }
+            "--signal" => {
+                i += 1;
+                let name = args.get(i).ok_or("--signal requires a value")?;
+                signal = platform::parse_signal(name).ok_or("--signal expects a known signal")?;
+            }
+            _ => break,
+        }
+        i += 1;
+    }
+
+    let command = args[i..].to_vec();
+    if command.is_empty() {
+        return Err("no command given");
+    }
+
+    Ok(Config {
+        giveup_timeout,
+        grace_period,
+        poll_interval,
+        signal,
+        command,
+    })
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: kill-orphan <command> [<args>...]");
+    let args: Vec<String> = env::args().skip(1).collect();
+    let config = parse_args(&args).unwrap_or_else(|_| {
+        eprintln!("{}", USAGE);
         exit(1);
-    }
+    });
 
     env_logger::init();
 
-    // Register signal handlers to intercept termination of this process
-    let catched_termination_signal = Arc::new(AtomicBool::new(false));
-    signal_hook::flag::register(
-        signal_hook::consts::SIGTERM,
-        Arc::clone(&catched_termination_signal),
-    )?;
-    signal_hook::flag::register(
-        signal_hook::consts::SIGINT,
-        Arc::clone(&catched_termination_signal),
-    )?;
-    signal_hook::flag::register(
-        signal_hook::consts::SIGQUIT,
-        Arc::clone(&catched_termination_signal),
-    )?;
-
-    debug!(
-        "Launching command: {:?}",
-        args.iter().skip(1).collect::<Vec<&String>>()
-    );
-
-    let mut cmd = Command::new(&args[1]);
+    // Register handlers to intercept termination of this process, and
+    // remember which one was caught so it can be forwarded to the child.
+    // Also covers `config.signal` itself, since that's what gets registered
+    // as our `PR_SET_PDEATHSIG` below.
+    platform::register_termination_handler(config.signal)?;
+
+    // Ask the OS to notify us the instant our parent dies, instead of only
+    // noticing up to a poll interval later via the sysinfo poll below. Uses
+    // the same signal `--signal`/`KILL_ORPHAN_SIGNAL` configures for the
+    // parent-death path, so that flag isn't silently ignored on the fast path.
+    platform::notify_on_parent_death(config.signal);
+
+    debug!("Launching command: {:?}", config.command);
+
+    let mut cmd = Command::new(&config.command[0]);
     cmd.stdout(Stdio::inherit());
     cmd.stderr(Stdio::inherit());
-    cmd.args(args.iter().skip(2).collect::<Vec<&String>>());
+    cmd.args(&config.command[1..]);
+    platform::prepare_command(&mut cmd);
 
     let mut subprocess = cmd.spawn()?;
+    let child_handle = platform::child_handle(&subprocess);
 
     debug!("Spawned process with pid {}", subprocess.id());
 
     let mut killed_subprocess_instant: Option<Instant> = None;
+    let mut escalated_to_force_kill = false;
 
     let mut sys = System::new();
     sys.refresh_processes();
@@ -65,66 +170,50 @@ fn main() -> Result<(), Box<dyn Error>> {
         .expect("Couldn't find my process information");
     let my_parent_pid = me.parent().expect("Couldn't find my parent PID");
 
-    let kill_all_children = |subprocess: &mut Child,
-                             killed_subprocess_instant: &mut Option<Instant>,
-                             sys: &mut System| {
-        sys.refresh_processes_specifics(ProcessRefreshKind::everything().with_cpu());
-        let sub_pid = Pid::from_u32(subprocess.id());
-        let mut children: HashSet<Pid> = HashSet::new();
-        loop {
-            let mut did_find_new_descendant = false;
-            for (pid, p) in sys.processes().iter() {
-                if let Some(parent_pid) = p.parent() {
-                    if !children.contains(pid)
-                        && (parent_pid == sub_pid || children.contains(&parent_pid))
-                    {
-                        children.insert(*pid);
-                        did_find_new_descendant = true;
-                    }
-                }
-            }
-            if !did_find_new_descendant {
-                break;
-            }
-        }
-
-        debug!("Killing main child process {}", subprocess.id());
-        subprocess.kill()?;
-
-        for pid in children {
-            debug!("Killing descendant of child {}", pid);
-            if let Some(process) = sys.process(pid) {
-                let _ = process.kill();
-            }
-        }
-
+    // Begins the two-phase shutdown by asking the child's whole process tree
+    // to exit gracefully. The main loop escalates to a hard kill after
+    // `config.grace_period` if it's still around.
+    let terminate_all_children = |signal: i32, killed_subprocess_instant: &mut Option<Instant>| {
+        platform::terminate(&child_handle, signal);
         *killed_subprocess_instant = Instant::now().into();
-
-        Ok::<(), std::io::Error>(())
     };
 
     loop {
         match killed_subprocess_instant {
             Some(instant) => {
-                if instant.elapsed().as_secs() > 5 {
-                    debug!("Process didn't exit after 5 seconds, giving up");
+                if instant.elapsed() > config.giveup_timeout {
+                    debug!(
+                        "Process didn't exit after {:?}, giving up",
+                        config.giveup_timeout
+                    );
                     exit(1)
                 }
+
+                if !escalated_to_force_kill && instant.elapsed() > config.grace_period {
+                    debug!("Process didn't exit within grace period, escalating");
+                    platform::force_kill(&child_handle);
+                    escalated_to_force_kill = true;
+                }
             }
             None => {
                 // Check if the signal handler catched a termination signal for this process
-                // If so, kill the child
+                // If so, forward it to the child
                 // At most 5s after the signal was catched, give up and exit
-                if catched_termination_signal.load(Ordering::Relaxed) {
+                // (5s is the default `config.giveup_timeout`; `--timeout` changes it)
+                //
+                // On Linux, PR_SET_PDEATHSIG delivers the parent's death through this
+                // very same handler, so this check and the parent-liveness poll below
+                // are no longer mutually exclusive the way they were before chunk0-4 -
+                // `else if` keeps us from signaling the child twice for one death.
+                let caught_signal = platform::caught_signal();
+                if caught_signal != 0 {
                     debug!("Received termination signal, killing process");
-                    kill_all_children(&mut subprocess, &mut killed_subprocess_instant, &mut sys)?;
-                }
-
-                // Check if parent is running
-                // refresh_process returns false when the given PID can't be found anymore
-                if !sys.refresh_process(my_parent_pid) {
+                    terminate_all_children(caught_signal, &mut killed_subprocess_instant);
+                } else if !sys.refresh_process(my_parent_pid) {
+                    // Check if parent is running
+                    // refresh_process returns false when the given PID can't be found anymore
                     debug!("Parent process doesn't exist anymore, killing process");
-                    kill_all_children(&mut subprocess, &mut killed_subprocess_instant, &mut sys)?;
+                    terminate_all_children(config.signal, &mut killed_subprocess_instant);
                 }
             }
         }
@@ -135,6 +224,6 @@ fn main() -> Result<(), Box<dyn Error>> {
             exit(status.code().unwrap_or(1));
         }
 
-        sleep(Duration::from_millis(100));
+        sleep(config.poll_interval);
     }
 }
@@ -0,0 +1,117 @@
+//! Unix backend: process groups and POSIX signals.
+
+use log::debug;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// Identifies the child's process group, so the whole tree it spawned can be
+/// signaled in one syscall instead of rediscovered by scanning `sysinfo`.
+pub struct ChildHandle {
+    pgid: sysinfo::Pid,
+}
+
+/// Signal sent when we only want to ask the child to shut down.
+pub const GRACEFUL_SIGNAL: i32 = libc::SIGTERM;
+
+static CAUGHT_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Puts the child in its own process group (pgid == pid, since `setsid()`
+/// also makes it a session leader) so it can later be signaled as a whole
+/// with a single `kill(-pgid, ...)`.
+pub fn prepare_command(cmd: &mut Command) {
+    unsafe {
+        cmd.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+}
+
+/// On Linux, ask the kernel to send us `signal` the instant our parent dies,
+/// which we pick up through the same signal handler as any other
+/// termination signal. Not available on other Unixes, which fall back to
+/// the sysinfo poll in `main`.
+pub fn notify_on_parent_death(signal: i32) {
+    #[cfg(target_os = "linux")]
+    unsafe {
+        libc::prctl(libc::PR_SET_PDEATHSIG, signal as libc::c_ulong);
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = signal;
+}
+
+pub fn child_handle(child: &Child) -> ChildHandle {
+    ChildHandle {
+        pgid: sysinfo::Pid::from_u32(child.id()),
+    }
+}
+
+/// Registers handlers for SIGTERM/SIGINT/SIGQUIT plus `extra_signal` (the
+/// configured `--signal`/`KILL_ORPHAN_SIGNAL`) that remember *which* signal
+/// was caught, so it can be forwarded to the child with the same semantics
+/// (e.g. an interactive Ctrl-C delivers SIGINT, not an unconditional
+/// SIGKILL). `extra_signal` needs a handler of its own too: `main` sets it
+/// as our `PR_SET_PDEATHSIG`, so without a handler the kernel would just
+/// terminate us outright on parent death instead of letting us catch it and
+/// forward it to the child.
+pub fn register_termination_handler(extra_signal: i32) -> Result<(), std::io::Error> {
+    let base_signals = [
+        signal_hook::consts::SIGTERM,
+        signal_hook::consts::SIGINT,
+        signal_hook::consts::SIGQUIT,
+    ];
+    let signals = base_signals
+        .into_iter()
+        .chain((!base_signals.contains(&extra_signal)).then_some(extra_signal));
+    for signal in signals {
+        unsafe {
+            signal_hook::low_level::register(signal, move || {
+                CAUGHT_SIGNAL.store(signal, Ordering::Relaxed);
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the last termination signal caught, or 0 if none has been.
+pub fn caught_signal() -> i32 {
+    CAUGHT_SIGNAL.load(Ordering::Relaxed)
+}
+
+fn send(handle: &ChildHandle, signal: i32) {
+    // Negative pid signals the whole process group in one syscall, so
+    // there's no need to separately track or signal each descendant.
+    unsafe {
+        libc::kill(-(handle.pgid.as_u32() as i32), signal);
+    }
+}
+
+pub fn terminate(handle: &ChildHandle, signal: i32) {
+    debug!(
+        "Terminating process group {} (signal {})",
+        handle.pgid.as_u32(),
+        signal
+    );
+    send(handle, signal);
+}
+
+pub fn force_kill(handle: &ChildHandle) {
+    debug!("Killing process group {} (SIGKILL)", handle.pgid.as_u32());
+    send(handle, libc::SIGKILL);
+}
+
+/// Parses a `--signal`/`KILL_ORPHAN_SIGNAL` value, accepting the common
+/// `SIGTERM`-style names (with or without the `SIG` prefix) or a raw number.
+pub fn parse_signal(name: &str) -> Option<i32> {
+    match name.to_ascii_uppercase().as_str() {
+        "SIGTERM" | "TERM" => Some(libc::SIGTERM),
+        "SIGKILL" | "KILL" => Some(libc::SIGKILL),
+        "SIGINT" | "INT" => Some(libc::SIGINT),
+        "SIGQUIT" | "QUIT" => Some(libc::SIGQUIT),
+        "SIGHUP" | "HUP" => Some(libc::SIGHUP),
+        "SIGUSR1" | "USR1" => Some(libc::SIGUSR1),
+        "SIGUSR2" | "USR2" => Some(libc::SIGUSR2),
+        _ => name.parse().ok(),
+    }
+}
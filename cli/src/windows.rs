@@ -0,0 +1,134 @@
+//! Windows backend: Job Objects, the Windows analogue of a Unix process
+//! group. Assigning the child to a job with
+//! `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE` lets the whole tree it spawns be torn
+//! down atomically, the same guarantee `[chunk0-1]` gets from `kill(-pgid)`
+//! on Unix.
+
+use log::debug;
+use std::os::windows::io::AsRawHandle;
+use std::os::windows::process::CommandExt;
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicI32, Ordering};
+use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::System::Console::{
+    GenerateConsoleCtrlEvent, SetConsoleCtrlHandler, CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT,
+    CTRL_C_EVENT, CTRL_LOGOFF_EVENT, CTRL_SHUTDOWN_EVENT,
+};
+use windows_sys::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+    SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+};
+use windows_sys::Win32::System::Threading::CREATE_NEW_PROCESS_GROUP;
+
+/// Wraps the Job Object the child (and anything it spawns) is assigned to,
+/// plus its pid, which doubles as its process group id since it was spawned
+/// with `CREATE_NEW_PROCESS_GROUP`.
+pub struct ChildHandle {
+    job: HANDLE,
+    pgid: u32,
+}
+
+/// Windows has no SIGTERM; CTRL_BREAK_EVENT is the closest equivalent a
+/// console process can install a handler for and react to before exiting.
+pub const GRACEFUL_SIGNAL: i32 = CTRL_BREAK_EVENT as i32;
+
+static CAUGHT_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Spawns the child into its own process group so a later
+/// `GenerateConsoleCtrlEvent` can target it without also hitting us.
+pub fn prepare_command(cmd: &mut Command) {
+    cmd.creation_flags(CREATE_NEW_PROCESS_GROUP);
+}
+
+/// No PR_SET_PDEATHSIG equivalent exists on Windows; the sysinfo poll in
+/// `main` is the only way we notice our parent exiting.
+pub fn notify_on_parent_death(_signal: i32) {}
+
+pub fn child_handle(child: &Child) -> ChildHandle {
+    unsafe {
+        let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const _,
+            std::mem::size_of_val(&info) as u32,
+        );
+        AssignProcessToJobObject(job, child.as_raw_handle() as HANDLE);
+        ChildHandle {
+            job,
+            pgid: child.id(),
+        }
+    }
+}
+
+unsafe extern "system" fn on_console_event(event: u32) -> i32 {
+    // Stored as event + 1 so the all-zero "unset" state of the atomic can't
+    // be confused with a caught CTRL_C_EVENT, which is itself 0.
+    CAUGHT_SIGNAL.store(event as i32 + 1, Ordering::Relaxed);
+    1 // handled
+}
+
+/// Installs a console control handler that remembers which event fired
+/// (Ctrl-C, Ctrl-Break, a close/logoff/shutdown request, ...) so it can
+/// inform the same escalation logic the Unix backend uses. Unlike the Unix
+/// backend, a single handler already catches every event unconditionally,
+/// so there's no separate signal to additionally register.
+pub fn register_termination_handler(_signal: i32) -> Result<(), std::io::Error> {
+    let installed = unsafe { SetConsoleCtrlHandler(Some(on_console_event), 1) };
+    if installed == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Returns the last console control event caught, or 0 if none has been.
+pub fn caught_signal() -> i32 {
+    match CAUGHT_SIGNAL.load(Ordering::Relaxed) {
+        0 => 0,
+        stored => stored - 1,
+    }
+}
+
+/// Asks the child's process group to exit gracefully via a console control
+/// event, the Windows analogue of a forwarded SIGTERM/SIGINT. The caller
+/// escalates to `force_kill` if the tree is still around after the grace
+/// period. Note only CTRL_C_EVENT and CTRL_BREAK_EVENT can actually be
+/// *generated* this way (CTRL_CLOSE/LOGOFF/SHUTDOWN_EVENT are receive-only),
+/// so an unrecognized `signal` falls back to CTRL_BREAK_EVENT.
+pub fn terminate(handle: &ChildHandle, signal: i32) {
+    let event = if signal as u32 == CTRL_C_EVENT {
+        CTRL_C_EVENT
+    } else {
+        CTRL_BREAK_EVENT
+    };
+    debug!(
+        "Sending console control event {} to process group {}",
+        event, handle.pgid
+    );
+    unsafe {
+        GenerateConsoleCtrlEvent(event, handle.pgid);
+    }
+}
+
+pub fn force_kill(handle: &ChildHandle) {
+    debug!("Terminating job object for child process tree");
+    unsafe {
+        TerminateJobObject(handle.job, 1);
+    }
+}
+
+/// Parses a `--signal`/`KILL_ORPHAN_SIGNAL` value, accepting the console
+/// control event names, the closest Unix signal name, or a raw number.
+pub fn parse_signal(name: &str) -> Option<i32> {
+    match name.to_ascii_uppercase().as_str() {
+        "CTRL_C_EVENT" | "SIGINT" | "INT" => Some(CTRL_C_EVENT as i32),
+        "CTRL_BREAK_EVENT" | "SIGTERM" | "TERM" => Some(CTRL_BREAK_EVENT as i32),
+        "CTRL_CLOSE_EVENT" => Some(CTRL_CLOSE_EVENT as i32),
+        "CTRL_LOGOFF_EVENT" => Some(CTRL_LOGOFF_EVENT as i32),
+        "CTRL_SHUTDOWN_EVENT" => Some(CTRL_SHUTDOWN_EVENT as i32),
+        _ => name.parse().ok(),
+    }
+}
@@ -17,6 +17,44 @@ fn usage() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn usage_flag_missing_value() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("kill-orphan")?;
+    cmd.arg("--timeout");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Usage: kill-orphan <command> [<args>...]",
+    ));
+    Ok(())
+}
+
+#[test]
+fn usage_flag_bad_value() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("kill-orphan")?;
+    cmd.arg("--poll-interval");
+    cmd.arg("not-a-number");
+    cmd.arg("sh");
+    cmd.arg("-c");
+    cmd.arg("true");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Usage: kill-orphan <command> [<args>...]",
+    ));
+    Ok(())
+}
+
+#[test]
+fn usage_unknown_signal() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("kill-orphan")?;
+    cmd.arg("--signal");
+    cmd.arg("NOT_A_SIGNAL");
+    cmd.arg("sh");
+    cmd.arg("-c");
+    cmd.arg("true");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Usage: kill-orphan <command> [<args>...]",
+    ));
+    Ok(())
+}
+
 #[test]
 fn stdout() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = Command::cargo_bin("kill-orphan")?;
@@ -131,11 +169,7 @@ fn kill_orphan_is_killed() -> Result<(), Box<dyn std::error::Error>> {
 
         assert!(next_line()
             .unwrap()
-            .contains(" Killing main child process "));
-
-        assert!(next_line()
-            .unwrap()
-            .contains(" Killing descendant of child "));
+            .contains(" Terminating process group "));
 
         assert!(next_line()
             .unwrap()
@@ -242,17 +276,15 @@ fn test_parent_dies() -> Result<(), Box<dyn std::error::Error>> {
         .assert()
         .failure();
 
+    // On Linux, PR_SET_PDEATHSIG delivers the parent's death as a signal, so
+    // it's caught and reported here rather than by the parent-liveness poll.
     assert!(next_line()
         .unwrap()
-        .contains(" Parent process doesn't exist anymore, killing process"));
+        .contains(" Received termination signal, killing process"));
 
     assert!(next_line()
         .unwrap()
-        .contains(" Killing main child process "));
-
-    assert!(next_line()
-        .unwrap()
-        .contains(" Killing descendant of child "));
+        .contains(" Terminating process group "));
 
     assert!(next_line()
         .unwrap()
@@ -272,3 +304,179 @@ fn test_parent_dies() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn giveup_timeout_and_grace_period_are_configurable() -> Result<(), Box<dyn std::error::Error>> {
+    let cmd = Command::cargo_bin("kill-orphan")?;
+    let kill_orphan_pid;
+    let pid;
+    {
+        let reader_handle = cmd!(
+            cmd.get_program(),
+            "--timeout",
+            "1",
+            "--grace-period",
+            "10",
+            "sh",
+            "-c",
+            "trap '' TERM; echo started; sleep 100"
+        )
+        .env("RUST_LOG", "trace")
+        .stderr_to_stdout()
+        .reader()?;
+
+        kill_orphan_pid = reader_handle.pids()[0];
+
+        let mut lines = BufReader::new(&reader_handle).lines();
+        let mut next_line = || {
+            let line = lines.next().unwrap()?;
+            println!("{}", line);
+            Ok::<String, Error>(line)
+        };
+
+        assert!(next_line().unwrap().contains("Launching command"));
+
+        let line_pid = next_line().unwrap();
+        assert!(line_pid.contains(" Spawned process with pid "));
+        pid = line_pid
+            .split_ascii_whitespace()
+            .last()
+            .unwrap()
+            .parse::<u32>()?;
+
+        assert_eq!(next_line().unwrap(), "started");
+
+        Command::new("kill")
+            .arg(format!("{}", kill_orphan_pid))
+            .assert()
+            .success();
+
+        assert!(next_line()
+            .unwrap()
+            .contains("Received termination signal, killing process"));
+        assert!(next_line().unwrap().contains(" Terminating process group "));
+
+        // The child traps SIGTERM and `--grace-period` (10s) is longer than
+        // `--timeout` (1s), so kill-orphan gives up well before it would
+        // ever escalate to SIGKILL - proving both flags actually apply.
+        assert!(next_line().unwrap().contains("giving up"));
+    }
+
+    // The child ignored SIGTERM and was never escalated to, so it's still
+    // running; clean it up so the test doesn't leak a sleeping process.
+    Command::new("kill")
+        .arg("-9")
+        .arg(format!("{}", pid))
+        .assert()
+        .success();
+
+    Ok(())
+}
+
+#[test]
+fn parent_death_uses_the_configured_signal() -> Result<(), Box<dyn std::error::Error>> {
+    let kill_orphan_cmd = Command::cargo_bin("kill-orphan")?;
+
+    let mut script_file = NamedTempFile::new()?;
+    write!(
+        script_file,
+        r#"
+        echo starting background
+        {} --signal SIGINT sh -c 'sleep 100' &
+        echo pid of background: $!
+        sleep 3
+        echo parent done
+        "#,
+        kill_orphan_cmd
+            .get_program()
+            .to_os_string()
+            .to_string_lossy()
+    )?;
+
+    let reader_handle = cmd!("sh", script_file.path())
+        .env("RUST_LOG", "trace")
+        .stderr_to_stdout()
+        .reader()?;
+
+    let mut lines = BufReader::new(&reader_handle).lines();
+    let mut next_line = || {
+        let line = lines.next().unwrap()?;
+        println!("{}", line);
+        Ok::<String, Error>(line)
+    };
+
+    assert_eq!(next_line().unwrap(), "starting background");
+    assert!(next_line().unwrap().contains("pid of background:"));
+    assert!(next_line()
+        .unwrap()
+        .contains(r#"Launching command: ["sh", "-c", "sleep 100"]"#));
+    assert!(next_line().unwrap().contains(" Spawned process with pid "));
+
+    reader_handle.kill()?;
+
+    // With `--signal SIGINT`, PR_SET_PDEATHSIG is configured with SIGINT (2)
+    // instead of the default SIGTERM (15), so that's what gets forwarded
+    // once the parent disappears.
+    assert!(next_line()
+        .unwrap()
+        .contains(" Received termination signal, killing process"));
+    assert!(next_line().unwrap().contains("(signal 2)"));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn parent_death_with_an_unregistered_signal_still_gets_caught() -> Result<(), Box<dyn std::error::Error>>
+{
+    // SIGUSR1 isn't one of the base SIGTERM/SIGINT/SIGQUIT signals
+    // register_termination_handler always listens for, so this would
+    // previously have had no handler installed for it: PR_SET_PDEATHSIG
+    // would have killed kill-orphan outright on parent death instead of
+    // letting it catch the signal and forward it to the child.
+    let kill_orphan_cmd = Command::cargo_bin("kill-orphan")?;
+
+    let mut script_file = NamedTempFile::new()?;
+    write!(
+        script_file,
+        r#"
+        echo starting background
+        {} --signal SIGUSR1 sh -c 'sleep 100' &
+        echo pid of background: $!
+        sleep 3
+        echo parent done
+        "#,
+        kill_orphan_cmd
+            .get_program()
+            .to_os_string()
+            .to_string_lossy()
+    )?;
+
+    let reader_handle = cmd!("sh", script_file.path())
+        .env("RUST_LOG", "trace")
+        .stderr_to_stdout()
+        .reader()?;
+
+    let mut lines = BufReader::new(&reader_handle).lines();
+    let mut next_line = || {
+        let line = lines.next().unwrap()?;
+        println!("{}", line);
+        Ok::<String, Error>(line)
+    };
+
+    assert_eq!(next_line().unwrap(), "starting background");
+    assert!(next_line().unwrap().contains("pid of background:"));
+    assert!(next_line()
+        .unwrap()
+        .contains(r#"Launching command: ["sh", "-c", "sleep 100"]"#));
+    assert!(next_line().unwrap().contains(" Spawned process with pid "));
+
+    reader_handle.kill()?;
+
+    assert!(next_line()
+        .unwrap()
+        .contains(" Received termination signal, killing process"));
+    assert!(next_line().unwrap().contains("(signal 10)"));
+
+    Ok(())
+}